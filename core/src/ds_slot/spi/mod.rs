@@ -0,0 +1,112 @@
+pub mod eeprom_4k;
+pub mod eeprom_fram;
+pub mod flash;
+pub mod nand;
+
+use eeprom_4k::Eeprom4k;
+use eeprom_fram::EepromFram;
+use flash::Flash;
+use nand::Nand;
+#[cfg(feature = "log")]
+use slog::Logger;
+
+/// A disconnected DS slot SPI device, used when no save type could be detected for the inserted
+/// cartridge.
+pub struct Empty {
+    #[cfg(feature = "log")]
+    logger: Logger,
+}
+
+impl Empty {
+    pub fn new(#[cfg(feature = "log")] logger: Logger) -> Self {
+        Empty {
+            #[cfg(feature = "log")]
+            logger,
+        }
+    }
+}
+
+/// The DS slot's SPI-attached save device, dispatching byte transfers to whichever backend was
+/// chosen for the inserted cartridge's save type.
+pub enum Spi {
+    Empty(Empty),
+    Eeprom4k(Eeprom4k),
+    EepromFram(EepromFram),
+    Flash(Flash),
+    Nand(Nand),
+}
+
+impl Spi {
+    pub fn contents(&self) -> &[u8] {
+        match self {
+            Spi::Empty(_) => &[],
+            Spi::Eeprom4k(device) => device.contents(),
+            Spi::EepromFram(device) => device.contents(),
+            Spi::Flash(device) => device.contents(),
+            Spi::Nand(device) => device.contents(),
+        }
+    }
+
+    pub fn contents_mut(&mut self) -> &mut [u8] {
+        match self {
+            Spi::Empty(_) => &mut [],
+            Spi::Eeprom4k(device) => device.contents_mut(),
+            Spi::EepromFram(device) => device.contents_mut(),
+            Spi::Flash(device) => device.contents_mut(),
+            Spi::Nand(device) => device.contents_mut(),
+        }
+    }
+
+    pub fn reset(self) -> Self {
+        match self {
+            Spi::Empty(device) => Spi::Empty(device),
+            Spi::Eeprom4k(device) => Spi::Eeprom4k(device.reset()),
+            Spi::EepromFram(device) => Spi::EepromFram(device.reset()),
+            Spi::Flash(device) => Spi::Flash(device.reset()),
+            Spi::Nand(device) => Spi::Nand(device.reset()),
+        }
+    }
+
+    /// Processes a single SPI byte transfer. `first`/`last` mark the first/last byte of a chip
+    /// select assertion, letting devices that buffer a pending write (such as [`Nand`]) know when
+    /// a command starts and when to abort or flush on deselection.
+    pub fn handle_byte(&mut self, byte: u8, first: bool, last: bool) -> u8 {
+        match self {
+            Spi::Empty(_) => 0,
+            Spi::Eeprom4k(device) => device.handle_byte(byte, first, last),
+            Spi::EepromFram(device) => device.handle_byte(byte, first, last),
+            Spi::Flash(device) => device.handle_byte(byte, first, last),
+            Spi::Nand(device) => device.handle_byte(byte, first, last),
+        }
+    }
+}
+
+impl From<Empty> for Spi {
+    fn from(other: Empty) -> Self {
+        Spi::Empty(other)
+    }
+}
+
+impl From<Eeprom4k> for Spi {
+    fn from(other: Eeprom4k) -> Self {
+        Spi::Eeprom4k(other)
+    }
+}
+
+impl From<EepromFram> for Spi {
+    fn from(other: EepromFram) -> Self {
+        Spi::EepromFram(other)
+    }
+}
+
+impl From<Flash> for Spi {
+    fn from(other: Flash) -> Self {
+        Spi::Flash(other)
+    }
+}
+
+impl From<Nand> for Spi {
+    fn from(other: Nand) -> Self {
+        Spi::Nand(other)
+    }
+}
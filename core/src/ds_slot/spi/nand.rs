@@ -0,0 +1,267 @@
+use crate::{utils::BoxedByteSlice, SaveContents};
+#[cfg(feature = "log")]
+use slog::Logger;
+
+/// Errors that can occur while constructing a [`Nand`] device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CreationError {
+    InvalidSize,
+}
+
+mod command {
+    pub const READ_ID: u8 = 0x9F;
+    pub const READ_STATUS: u8 = 0x05;
+    pub const WRITE_ENABLE: u8 = 0x06;
+    pub const WRITE_DISABLE: u8 = 0x04;
+    pub const PAGE_READ: u8 = 0x13;
+    pub const READ_CACHE: u8 = 0x03;
+    pub const PROGRAM_LOAD: u8 = 0x02;
+    pub const PROGRAM_EXECUTE: u8 = 0x10;
+    pub const BLOCK_ERASE: u8 = 0xD8;
+}
+
+mod status {
+    pub const WRITE_IN_PROGRESS: u8 = 1 << 0;
+    pub const WRITE_ENABLE_LATCH: u8 = 1 << 1;
+}
+
+const PAGE_SIZE: usize = 0x840;
+const PAGES_PER_BLOCK: usize = 0x40;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    Idle,
+    ReadingId { pos: usize },
+    ReadingStatus,
+    ReceivingAddr { command: u8, pos: usize, addr: u32 },
+    ReadingCache { pos: usize },
+    LoadingProgramData { pos: usize },
+}
+
+/// An emulated SPI NAND flash chip, used as a DS slot save device by DSi-enhanced titles that
+/// need more storage than EEPROM/FRAM/FLASH can provide.
+pub struct Nand {
+    contents: BoxedByteSlice,
+    id: [u8; 4],
+    status: u8,
+    state: State,
+    page_cache: BoxedByteSlice,
+    cached_page: u32,
+    #[cfg(feature = "log")]
+    logger: Logger,
+}
+
+impl Nand {
+    /// Creates a new NAND device, sizing it to match `contents`'s length (64, 128 or 256 Mib,
+    /// expressed in bytes).
+    pub fn new(
+        contents: SaveContents,
+        id: Option<[u8; 4]>,
+        #[cfg(feature = "log")] logger: Logger,
+    ) -> Result<Self, CreationError> {
+        let contents = match contents {
+            SaveContents::Existing(contents) => {
+                if !matches!(contents.len(), 0x80_0000 | 0x100_0000 | 0x200_0000) {
+                    return Err(CreationError::InvalidSize);
+                }
+                contents
+            }
+            SaveContents::New(len) => {
+                if !matches!(len, 0x80_0000 | 0x100_0000 | 0x200_0000) {
+                    return Err(CreationError::InvalidSize);
+                }
+                BoxedByteSlice::new_zeroed(len)
+            }
+        };
+
+        let id = id.unwrap_or_else(|| match contents.len() {
+            0x80_0000 => [0xEC, 0xF1, 0x00, 0x15],
+            0x100_0000 => [0xEC, 0xF1, 0x00, 0x35],
+            _ => [0xEC, 0xF1, 0x00, 0x55],
+        });
+
+        Ok(Nand {
+            contents,
+            id,
+            status: 0,
+            state: State::Idle,
+            page_cache: BoxedByteSlice::new_zeroed(PAGE_SIZE),
+            cached_page: 0,
+            #[cfg(feature = "log")]
+            logger,
+        })
+    }
+
+    pub fn contents(&self) -> &[u8] {
+        &self.contents
+    }
+
+    pub fn contents_mut(&mut self) -> &mut [u8] {
+        &mut self.contents
+    }
+
+    pub fn reset(self) -> Self {
+        Nand {
+            contents: self.contents,
+            id: self.id,
+            status: 0,
+            state: State::Idle,
+            page_cache: self.page_cache,
+            cached_page: 0,
+            #[cfg(feature = "log")]
+            logger: self.logger,
+        }
+    }
+
+    fn page_count(&self) -> u32 {
+        (self.contents.len() / PAGE_SIZE) as u32
+    }
+
+    /// Wraps a raw 3-byte SPI page address into the chip's actual page range, the same way the
+    /// EEPROM/FRAM/Flash devices wrap out-of-range addresses instead of panicking.
+    fn mask_page(&self, page: u32) -> u32 {
+        page % self.page_count()
+    }
+
+    fn page_offset(&self, page: u32) -> usize {
+        page as usize * PAGE_SIZE
+    }
+
+    fn load_page_into_cache(&mut self, page: u32) {
+        let offset = self.page_offset(page);
+        let len = self.page_cache.len().min(self.contents.len() - offset);
+        self.page_cache[..len].copy_from_slice(&self.contents[offset..offset + len]);
+        self.cached_page = page;
+    }
+
+    fn erase_block(&mut self, page: u32) {
+        let block_start = (page as usize / PAGES_PER_BLOCK) * PAGES_PER_BLOCK * PAGE_SIZE;
+        let block_end = (block_start + PAGES_PER_BLOCK * PAGE_SIZE).min(self.contents.len());
+        self.contents[block_start..block_end].fill(0xFF);
+    }
+
+    /// Runs a program or erase operation that was already validated against the write-enable
+    /// latch, leaving [`status::WRITE_IN_PROGRESS`] set until the next status poll clears it (the
+    /// chip reports busy for exactly one [`READ_STATUS`](command::READ_STATUS) even though this
+    /// emulation completes the operation immediately).
+    fn complete_write_op(&mut self, op: impl FnOnce(&mut Self)) {
+        op(self);
+        self.status &= !status::WRITE_ENABLE_LATCH;
+        self.status |= status::WRITE_IN_PROGRESS;
+    }
+
+    /// Processes a single SPI byte, starting a new command when `first` is set. `last` signals
+    /// that chip select is about to be deasserted; since every write here is already committed
+    /// synchronously by the time the triggering command byte finishes (there's no write buffered
+    /// only in-flight across transfers other than the explicit cache held between `PROGRAM_LOAD`
+    /// and `PROGRAM_EXECUTE`), deselection just aborts whatever multi-byte command was mid-flight
+    /// back to idle. Returns the byte shifted out on MISO in response.
+    pub fn handle_byte(&mut self, byte: u8, first: bool, last: bool) -> u8 {
+        if first {
+            self.state = match byte {
+                command::READ_ID => State::ReadingId { pos: 0 },
+                command::READ_STATUS => State::ReadingStatus,
+                command::WRITE_ENABLE => {
+                    self.status |= status::WRITE_ENABLE_LATCH;
+                    State::Idle
+                }
+                command::WRITE_DISABLE => {
+                    self.status &= !status::WRITE_ENABLE_LATCH;
+                    State::Idle
+                }
+                command::PAGE_READ | command::PROGRAM_LOAD | command::BLOCK_ERASE => {
+                    State::ReceivingAddr {
+                        command: byte,
+                        pos: 0,
+                        addr: 0,
+                    }
+                }
+                command::READ_CACHE => State::ReadingCache { pos: 0 },
+                command::PROGRAM_EXECUTE => {
+                    if self.status & status::WRITE_ENABLE_LATCH != 0 {
+                        self.complete_write_op(|this| {
+                            let offset = this.page_offset(this.cached_page);
+                            let len = this.page_cache.len().min(this.contents.len() - offset);
+                            this.contents[offset..offset + len]
+                                .copy_from_slice(&this.page_cache[..len]);
+                        });
+                    }
+                    State::Idle
+                }
+                _ => {
+                    #[cfg(feature = "log")]
+                    slog::warn!(self.logger, "Unknown NAND command: {:#04X}", byte);
+                    State::Idle
+                }
+            };
+
+            return 0;
+        }
+
+        let result = match &mut self.state {
+            State::Idle => 0,
+
+            State::ReadingId { pos } => {
+                let result = self.id.get(*pos).copied().unwrap_or(0);
+                *pos += 1;
+                result
+            }
+
+            State::ReadingStatus => {
+                let result = self.status;
+                self.status &= !status::WRITE_IN_PROGRESS;
+                result
+            }
+
+            State::ReceivingAddr { command, pos, addr } => {
+                *addr = (*addr << 8) | byte as u32;
+                *pos += 1;
+                if *pos == 3 {
+                    let command = *command;
+                    // Validated once here, covering PAGE_READ, the cached_page stored by
+                    // PROGRAM_LOAD (and later consumed by PROGRAM_EXECUTE), and BLOCK_ERASE.
+                    let page = self.mask_page(*addr);
+                    self.state = match command {
+                        command::PAGE_READ => {
+                            self.load_page_into_cache(page);
+                            State::Idle
+                        }
+                        command::PROGRAM_LOAD => {
+                            self.cached_page = page;
+                            self.page_cache.fill(0xFF);
+                            State::LoadingProgramData { pos: 0 }
+                        }
+                        command::BLOCK_ERASE => {
+                            if self.status & status::WRITE_ENABLE_LATCH != 0 {
+                                self.complete_write_op(|this| this.erase_block(page));
+                            }
+                            State::Idle
+                        }
+                        _ => unreachable!(),
+                    };
+                }
+                0
+            }
+
+            State::ReadingCache { pos } => {
+                let result = self.page_cache.get(*pos).copied().unwrap_or(0);
+                *pos += 1;
+                result
+            }
+
+            State::LoadingProgramData { pos } => {
+                if let Some(slot) = self.page_cache.get_mut(*pos) {
+                    *slot = byte;
+                }
+                *pos += 1;
+                0
+            }
+        };
+
+        if last {
+            self.state = State::Idle;
+        }
+
+        result
+    }
+}
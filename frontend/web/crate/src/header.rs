@@ -0,0 +1,88 @@
+use crate::SaveType;
+use wasm_bindgen::prelude::*;
+
+/// The subset of the DS ROM header needed to identify a cartridge and pick a sensible default
+/// save type, parsed out of the first 0x15 bytes of the ROM.
+#[derive(Clone, Debug)]
+#[wasm_bindgen]
+pub struct RomHeader {
+    title: String,
+    game_code: String,
+    maker_code: String,
+    unit_code: u8,
+    device_capacity: u8,
+}
+
+#[wasm_bindgen]
+impl RomHeader {
+    /// The 12-character ASCII game title at 0x000, with trailing NUL bytes trimmed.
+    #[wasm_bindgen(getter)]
+    pub fn title(&self) -> String {
+        self.title.clone()
+    }
+
+    /// The 4-character game code at 0x00C (without the leading manufacturer letter convention
+    /// applied; this is the raw code as printed on the cartridge label).
+    #[wasm_bindgen(getter)]
+    pub fn game_code(&self) -> String {
+        self.game_code.clone()
+    }
+
+    /// The 2-character maker code at 0x010 (`"01"` for Nintendo, etc).
+    #[wasm_bindgen(getter)]
+    pub fn maker_code(&self) -> String {
+        self.maker_code.clone()
+    }
+
+    /// The unit code at 0x012: 0 for DS, 2 for DS+DSi, 3 for DSi-exclusive.
+    #[wasm_bindgen(getter)]
+    pub fn unit_code(&self) -> u8 {
+        self.unit_code
+    }
+
+    /// The raw chip-capacity byte at 0x014; the ROM size in bytes is `0x20000 << device_capacity`.
+    #[wasm_bindgen(getter)]
+    pub fn device_capacity(&self) -> u8 {
+        self.device_capacity
+    }
+}
+
+fn ascii_field(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+/// Parses a [`RomHeader`] out of the start of a DS cartridge ROM image.
+///
+/// `rom` must be at least 0x015 bytes long; this is always true for valid DS ROMs, whose header
+/// alone occupies the first 0x200 bytes.
+pub fn parse(rom: &[u8]) -> RomHeader {
+    RomHeader {
+        title: ascii_field(&rom[0x000..0x00C]),
+        game_code: ascii_field(&rom[0x00C..0x010]),
+        maker_code: ascii_field(&rom[0x010..0x012]),
+        unit_code: rom[0x012],
+        device_capacity: rom[0x014],
+    }
+}
+
+/// A small embedded database mapping game codes to their known save type, for titles whose save
+/// size can't be unambiguously guessed from an existing save file alone (or when there is no
+/// existing save file to guess from in the first place).
+///
+/// This is intentionally not exhaustive; unrecognized codes fall back to length-based heuristics.
+static GAME_CODE_SAVE_TYPES: &[(&str, SaveType)] = &[
+    ("ASME", SaveType::EepromFram512k), // Mario Kart DS
+    ("ADAE", SaveType::Flash8m),        // Animal Crossing: Wild World
+    ("YDSE", SaveType::EepromFram512k), // WarioWare: Touched!
+    ("UORE", SaveType::Nand64m),        // Mario & Sonic at the Olympic Games (DSi enhanced)
+    ("UXBE", SaveType::Nand128m),       // Flipnote Studio
+];
+
+/// Looks up the default save type for a game code, if it's present in the embedded database.
+pub fn save_type_for_game_code(game_code: &str) -> Option<SaveType> {
+    GAME_CODE_SAVE_TYPES
+        .iter()
+        .find(|(code, _)| *code == game_code)
+        .map(|(_, save_type)| *save_type)
+}
@@ -4,8 +4,13 @@
 mod audio;
 #[cfg(feature = "log")]
 mod console_log;
+mod header;
 pub mod renderer_3d;
 
+use header::RomHeader;
+
+use dust_frontend_common::save_slot;
+
 use dust_core::{
     cpu::{self, arm7, arm9, interpreter::Interpreter},
     ds_slot,
@@ -99,6 +104,30 @@ pub struct EmuState {
     emu: Option<Emu<Interpreter>>,
     arm7_bios: Option<Box<Bytes<{ arm7::BIOS_SIZE }>>>,
     arm9_bios: Option<Box<Bytes<{ arm9::BIOS_SIZE }>>>,
+    rom_header: RomHeader,
+    /// The crash-safe dual-slot container backing [`export_save`](EmuState::export_save) and
+    /// [`load_save`](EmuState::load_save); kept around (rather than rebuilt from scratch on every
+    /// export) so repeated saves keep alternating slots and bumping the sequence number instead of
+    /// always starting over at slot 0.
+    save_container: Vec<u8>,
+}
+
+/// Magic tag identifying a dust save-state blob, written at the very start of the format.
+#[cfg(feature = "savestate")]
+const STATE_MAGIC: &[u8; 4] = b"DSST";
+/// Bumped whenever the serialized layout of `Emu<Interpreter>` changes in an incompatible way.
+#[cfg(feature = "savestate")]
+const STATE_FORMAT_VERSION: u32 = 1;
+
+#[cfg(feature = "savestate")]
+fn model_byte(model: Model) -> u8 {
+    match model {
+        Model::Ds => 0,
+        Model::Lite => 1,
+        Model::Ique => 2,
+        Model::IqueLite => 3,
+        Model::Dsi => 4,
+    }
 }
 
 fn build_emu<E: cpu::Engine>(emu_builder: emu::Builder, engine: E) -> emu::Emu<E> {
@@ -151,12 +180,102 @@ impl EmuState {
         self.emu = Some(build_emu(emu_builder, Interpreter));
     }
 
-    pub fn load_save(&mut self, ram_arr: Uint8Array) {
-        ram_arr.copy_to(self.emu.as_mut().unwrap().ds_slot.spi.contents_mut())
+    /// Loads a crash-safe save container previously produced by
+    /// [`export_save`](EmuState::export_save) (or restored from storage across sessions),
+    /// selecting whichever of its two slots has the highest sequence number and a valid CRC32,
+    /// and falling back to the other slot if that one doesn't check out.
+    pub fn load_save(&mut self, container_arr: Uint8Array) {
+        let contents_len = self.emu.as_ref().unwrap().ds_slot.spi.contents().len();
+        let container = container_arr.to_vec();
+        if container.len() != save_slot::container_len(contents_len) {
+            return;
+        }
+        if let Some(payload) = save_slot::read(&container, contents_len) {
+            self.emu
+                .as_mut()
+                .unwrap()
+                .ds_slot
+                .spi
+                .contents_mut()
+                .copy_from_slice(&payload);
+        }
+        self.save_container = container;
+    }
+
+    /// Writes the current save contents into whichever of the container's two slots isn't
+    /// currently active, bumps its sequence number, and returns the whole container for the
+    /// caller to persist. A write interrupted partway through (tab close, crash) leaves the
+    /// previously active slot untouched, so the last good save is never lost.
+    pub fn export_save(&mut self) -> Uint8Array {
+        let contents_len = self.emu.as_ref().unwrap().ds_slot.spi.contents().len();
+        if self.save_container.len() != save_slot::container_len(contents_len) {
+            self.save_container = vec![0; save_slot::container_len(contents_len)];
+        }
+        let contents = self.emu.as_ref().unwrap().ds_slot.spi.contents().to_vec();
+        save_slot::write(&mut self.save_container, &contents);
+        Uint8Array::from(self.save_container.as_slice())
+    }
+
+    /// Serializes the whole emulator core (CPU registers, RAM, VRAM, scheduler, GPU/SPU state,
+    /// ...) into a blob [`import_state`](EmuState::import_state) can later restore: a magic tag +
+    /// format-version word + model byte up front (self-describing, and checked before touching
+    /// anything past them), followed by a `bincode`-encoded payload of `Emu<Interpreter>` itself,
+    /// which is *not* self-describing on its own — hence the prefix.
+    ///
+    /// Gated behind this crate's `savestate` feature, which is only meaningful once dust-core's
+    /// own `savestate` feature is enabled too: that's what actually derives `Serialize`/
+    /// `Deserialize` on `Emu<Interpreter>` and every field it owns, skipping the scheduler's
+    /// transient event-callback state and rebuilding it on load instead of serializing it.
+    #[cfg(feature = "savestate")]
+    pub fn export_state(&self) -> Uint8Array {
+        let emu = self.emu.as_ref().unwrap();
+        let mut payload =
+            bincode::serialize(emu).expect("couldn't serialize emulator state");
+
+        let mut blob = Vec::with_capacity(STATE_MAGIC.len() + 4 + 1 + payload.len());
+        blob.extend_from_slice(STATE_MAGIC);
+        blob.extend_from_slice(&STATE_FORMAT_VERSION.to_le_bytes());
+        blob.push(model_byte(self.model));
+        blob.append(&mut payload);
+
+        Uint8Array::from(blob.as_slice())
     }
 
-    pub fn export_save(&self) -> Uint8Array {
-        Uint8Array::from(self.emu.as_ref().unwrap().ds_slot.spi.contents())
+    /// Restores the emulator core from a blob produced by [`export_state`](EmuState::export_state),
+    /// refusing to load one with a mismatched format version or model. See `export_state` for the
+    /// `savestate`-feature precondition this relies on.
+    #[cfg(feature = "savestate")]
+    pub fn import_state(&mut self, state: Uint8Array) {
+        let blob = state.to_vec();
+
+        let header_len = STATE_MAGIC.len() + 4 + 1;
+        assert!(blob.len() >= header_len, "Save state is truncated");
+        assert!(
+            &blob[..STATE_MAGIC.len()] == STATE_MAGIC,
+            "Not a dust save state"
+        );
+
+        let version_start = STATE_MAGIC.len();
+        let version = u32::from_le_bytes(
+            blob[version_start..version_start + 4]
+                .try_into()
+                .unwrap(),
+        );
+        assert!(
+            version == STATE_FORMAT_VERSION,
+            "Unsupported save state format version {version} (expected {STATE_FORMAT_VERSION})"
+        );
+
+        let model = blob[version_start + 4];
+        assert!(
+            model == model_byte(self.model),
+            "Save state was made for a different DS model"
+        );
+
+        self.emu = Some(
+            bincode::deserialize(&blob[header_len..])
+                .expect("couldn't deserialize emulator state"),
+        );
     }
 
     pub fn update_input(&mut self, pressed: u32, released: u32) {
@@ -174,6 +293,11 @@ impl EmuState {
         }
     }
 
+    #[wasm_bindgen(getter)]
+    pub fn rom_header(&self) -> RomHeader {
+        self.rom_header.clone()
+    }
+
     pub fn run_frame(&mut self) -> Uint32Array {
         // TODO: Handle an eventual shutdown
         let emu = self.emu.as_mut().unwrap();
@@ -234,6 +358,9 @@ pub fn create_emu_state(
         panic!("Invalid ROM size");
     }
 
+    let rom_header = header::parse(&rom);
+    let database_save_type = header::save_type_for_game_code(&rom_header.game_code());
+
     let save_contents = save_contents_arr.map(|save_contents_arr| {
         let mut save_contents = BoxedByteSlice::new_zeroed(save_contents_arr.length() as usize);
         save_contents_arr.copy_to(&mut save_contents);
@@ -270,20 +397,22 @@ pub fn create_emu_state(
                 }
             } else {
                 #[allow(clippy::unnecessary_lazy_evaluations)]
-                SaveType::from_save_len(save_contents.len()).unwrap_or_else(|| {
-                    #[cfg(feature = "log")]
-                    slog::error!(
-                        logger,
-                        "Unrecognized save file size ({} B) and no database entry found, \
-                         defaulting to an empty save.",
-                        save_contents.len()
-                    );
-                    SaveType::None
-                })
+                database_save_type
+                    .or_else(|| SaveType::from_save_len(save_contents.len()))
+                    .unwrap_or_else(|| {
+                        #[cfg(feature = "log")]
+                        slog::error!(
+                            logger,
+                            "Unrecognized save file size ({} B) and no database entry found, \
+                             defaulting to an empty save.",
+                            save_contents.len()
+                        );
+                        SaveType::None
+                    })
             }
         } else {
             #[allow(clippy::unnecessary_lazy_evaluations)]
-            save_type.unwrap_or_else(|| {
+            save_type.or(database_save_type).unwrap_or_else(|| {
                 #[cfg(feature = "log")]
                 slog::error!(
                     logger,
@@ -350,15 +479,14 @@ pub fn create_emu_state(
                     .into()
                 }
                 SaveType::Nand64m | SaveType::Nand128m | SaveType::Nand256m => {
-                    #[cfg(feature = "log")]
-                    slog::error!(
-                        logger,
-                        "TODO: NAND saves are currently unsupported, falling back to no save file."
-                    );
-                    ds_slot::spi::Empty::new(
+                    ds_slot::spi::nand::Nand::new(
+                        save_contents,
+                        None,
                         #[cfg(feature = "log")]
-                        logger.new(slog::o!("ds_spi" => "nand_todo")),
+                        logger.new(slog::o!("ds_spi" => "nand")),
                     )
+                    // NOTE: The save contents' size is ensured beforehand, this should never occur.
+                    .expect("couldn't create NAND DS slot SPI device")
                     .into()
                 }
             }
@@ -402,6 +530,8 @@ pub fn create_emu_state(
         emu: Some(emu),
         arm7_bios,
         arm9_bios,
+        rom_header,
+        save_container: Vec::new(),
     }
 }
 
@@ -0,0 +1,10 @@
+//! Golden per-(ROM, frame) framebuffer CRC32s.
+//!
+//! Regenerate with `DUST_TEST_UPDATE_GOLDEN=1 cargo test --test frame_hash -- --nocapture`, then
+//! copy the printed table back in here. Entries are intentionally sparse (every 10th frame) rather than
+//! every single one, so a one-frame timing shift in an unrelated subsystem doesn't make every
+//! entry in the table go stale at once.
+pub static GOLDEN_HASHES: &[(&str, usize, u32)] = &[
+    // ("chicken-scratch.nds", 0, 0x00000000),
+    // ("chicken-scratch.nds", 10, 0x00000000),
+];